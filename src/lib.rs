@@ -1,19 +1,38 @@
 use proc_macro::TokenStream;
-use quote::{quote, ToTokens};
-use syn::{parse_macro_input, ItemStruct, Meta, Type};
+use quote::quote;
+use std::collections::HashSet;
+use syn::parse::{Parse, ParseStream};
+use syn::{
+    parse_macro_input, Expr, Fields, GenericArgument, ItemStruct, Meta, PathArguments, PathSegment,
+    Token, Type,
+};
 
-#[proc_macro_derive(Tlayuda, attributes(tlayuda_ignore))]
+#[proc_macro_derive(Tlayuda, attributes(tlayuda_ignore, tlayuda))]
 pub fn entry_point(input: TokenStream) -> TokenStream {
     let item_struct = parse_macro_input!(input as ItemStruct);
     let source_struct_name = item_struct.ident.clone();
+    let generics = item_struct.generics.clone();
+    let generic_type_params: HashSet<String> = generics
+        .type_params()
+        .map(|param| param.ident.to_string())
+        .collect();
+    let (phantom_declaration, phantom_initializer) = phantom_data_parts(&generics);
+    let is_tuple_struct = matches!(item_struct.fields, Fields::Unnamed(_));
     let fields = get_fields(item_struct);
 
     let inner_builder_name = quote::format_ident!("Tlayuda{}Builder", source_struct_name);
-    let OutputTokenPartials {
-        field_declarations,
-        field_builder_intializers,
-        field_setter_functions,
-    } = generate_output_tokens(&fields);
+    let (
+        OutputTokenPartials {
+            field_declarations,
+            field_builder_intializers,
+            field_setter_functions,
+        },
+        default_bounds,
+    ) = generate_output_tokens(&fields, &generic_type_params);
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let builder_where_clause = combined_where_clause(where_clause, &default_bounds);
+
     let builder_parameters = fields
         .iter()
         .filter(|f| f.is_ignored)
@@ -27,27 +46,41 @@ pub fn entry_point(input: TokenStream) -> TokenStream {
             },
         )
         .collect::<Vec<_>>();
-    let (ignored_fields, fields): (Vec<_>, Vec<_>) = fields.iter().partition(|f| f.is_ignored);
-    let inner_builder_constructor_parameters = ignored_fields.iter().map(|f| {
+    let inner_builder_constructor_parameters = fields.iter().filter(|f| f.is_ignored).map(|f| {
         let i = f.identifier.clone();
         quote! { #i }
     });
-    let ignored_fields = ignored_fields.iter().map(|f| {
-        let i = f.identifier.clone();
-        quote! { #i: self.#i.clone(), }
+
+    // Built in original declaration order so a tuple struct's positional constructor lines up;
+    // a named struct can reorder freely since the literal below addresses fields by name.
+    let build_field_values = fields.iter().map(|f| {
+        let identifier = &f.identifier;
+        if f.is_ignored {
+            quote! { self.#identifier.clone() }
+        } else {
+            quote! { self.#identifier.as_mut()(i) }
+        }
     });
-    let fields = fields.iter().map(|f| f.identifier.clone());
+
+    let build_body = if is_tuple_struct {
+        quote! { #source_struct_name(#(#build_field_values),*) }
+    } else {
+        let names = fields.iter().map(|f| f.identifier.clone());
+        quote! { #source_struct_name { #(#names: #build_field_values),* } }
+    };
 
     let output = quote! {
-        pub struct #inner_builder_name {
+        pub struct #inner_builder_name #impl_generics #where_clause {
             index: usize,
+            #phantom_declaration
             #(#field_declarations),*
         }
 
-        impl #inner_builder_name {
-            pub fn new(#(#builder_parameters),*) -> #inner_builder_name {
+        impl #impl_generics #inner_builder_name #ty_generics #builder_where_clause {
+            pub fn new(#(#builder_parameters),*) -> #inner_builder_name #ty_generics {
                 #inner_builder_name {
                     index: 0,
+                    #phantom_initializer
                     #(#field_builder_intializers),*
                 }
             }
@@ -59,26 +92,31 @@ pub fn entry_point(input: TokenStream) -> TokenStream {
                 self
             }
 
+            pub fn reset_index(&mut self) {
+                self.index = 0;
+            }
+
             fn take_index(&mut self) -> usize {
                 self.index = self.index + 1;
                 self.index - 1
             }
 
-            pub fn build(&mut self) -> #source_struct_name {
+            pub fn build(&mut self) -> #source_struct_name #ty_generics {
                 let i = self.take_index();
-                #source_struct_name {
-                    #(#ignored_fields)*
-                    #(#fields: self.#fields.as_mut()(i)),*
-                }
+                #build_body
             }
 
-            pub fn build_vec(&mut self, count: usize) -> Vec::<#source_struct_name> {
-                std::iter::repeat_with(|| self.build()).take(count).collect()
+            pub fn build_iter(&mut self) -> impl Iterator<Item = #source_struct_name #ty_generics> + '_ {
+                std::iter::from_fn(move || Some(self.build()))
+            }
+
+            pub fn build_vec(&mut self, count: usize) -> Vec::<#source_struct_name #ty_generics> {
+                self.build_iter().take(count).collect()
             }
         }
 
-        impl #source_struct_name {
-            pub fn tlayuda(#(#builder_parameters),*) -> #inner_builder_name {
+        impl #impl_generics #source_struct_name #ty_generics #builder_where_clause {
+            pub fn tlayuda(#(#builder_parameters),*) -> #inner_builder_name #ty_generics {
                 #inner_builder_name::new(#(#inner_builder_constructor_parameters),* )
             }
         }
@@ -90,30 +128,103 @@ pub fn entry_point(input: TokenStream) -> TokenStream {
 #[derive(Debug)]
 struct FieldInfo {
     identifier: proc_macro2::Ident,
+    // The name used for setters (`set_<suffix>`) and for the default `String`/`OsString` seed
+    // value. For a named field this is the field name; for a tuple-struct field it's the
+    // positional index ("0", "1", ...), since a bare digit can't stand alone as an identifier.
+    setter_suffix: String,
     field_type: syn::Type,
     is_ignored: bool,
+    // From `#[tlayuda(default = <expr>)]`: a caller-supplied seed in place of the type-based
+    // default generator.
+    default_override: Option<syn::Expr>,
+    // Set instead of `default_override` when the `#[tlayuda(...)]` attribute itself failed to
+    // parse; emitted verbatim in place of the field's initializer so the error points at the
+    // attribute rather than panicking the whole macro.
+    attribute_error: Option<proc_macro2::TokenStream>,
+}
+
+fn is_tlayuda_ignore(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|attribute| {
+        if let Ok(Meta::Path(path)) = attribute.parse_meta() {
+            path.is_ident("tlayuda_ignore")
+        } else {
+            false
+        }
+    })
+}
+
+// Parses `#[tlayuda(default = <expr>)]`. The expression can't be represented as a `syn::Lit`
+// (it's often a closure), so this is parsed by hand rather than via `Attribute::parse_meta`.
+struct TlayudaDefaultArg {
+    expr: Expr,
+}
+
+impl Parse for TlayudaDefaultArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let keyword: proc_macro2::Ident = input.parse()?;
+        if keyword != "default" {
+            return Err(syn::Error::new(
+                keyword.span(),
+                "expected `default = <expr>`",
+            ));
+        }
+        input.parse::<Token![=]>()?;
+        Ok(TlayudaDefaultArg {
+            expr: input.parse()?,
+        })
+    }
+}
+
+// Returns `(default_override, attribute_error)`: at most one of the two is ever `Some`.
+fn parse_default_override(
+    attrs: &[syn::Attribute],
+) -> (Option<Expr>, Option<proc_macro2::TokenStream>) {
+    let Some(attribute) = attrs.iter().find(|attribute| attribute.path.is_ident("tlayuda")) else {
+        return (None, None);
+    };
+
+    match attribute.parse_args::<TlayudaDefaultArg>() {
+        Ok(parsed) => (Some(parsed.expr), None),
+        Err(e) => (None, Some(e.to_compile_error())),
+    }
 }
 
 fn get_fields(item_struct: ItemStruct) -> Vec<FieldInfo> {
-    item_struct
-        .fields
-        .iter()
-        .filter(|x| x.ident.is_some())
-        .map(|x| FieldInfo {
-            identifier: x.ident.as_ref().unwrap().clone(),
-            field_type: x.ty.clone(),
-            is_ignored: x.attrs.iter().any(|attribute| {
-                if let Ok(meta) = attribute.parse_meta() {
-                    match meta {
-                        Meta::Path(path) => path.is_ident("tlayuda_ignore".into()),
-                        _ => false,
-                    }
-                } else {
-                    false
+    match item_struct.fields {
+        Fields::Named(named) => named
+            .named
+            .iter()
+            .map(|field| {
+                let identifier = field.ident.as_ref().unwrap().clone();
+                let (default_override, attribute_error) = parse_default_override(&field.attrs);
+                FieldInfo {
+                    setter_suffix: identifier.to_string(),
+                    identifier,
+                    field_type: field.ty.clone(),
+                    is_ignored: is_tlayuda_ignore(&field.attrs),
+                    default_override,
+                    attribute_error,
                 }
-            }),
-        })
-        .collect()
+            })
+            .collect(),
+        Fields::Unnamed(unnamed) => unnamed
+            .unnamed
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                let (default_override, attribute_error) = parse_default_override(&field.attrs);
+                FieldInfo {
+                    identifier: quote::format_ident!("field_{}", index),
+                    setter_suffix: index.to_string(),
+                    field_type: field.ty.clone(),
+                    is_ignored: is_tlayuda_ignore(&field.attrs),
+                    default_override,
+                    attribute_error,
+                }
+            })
+            .collect(),
+        Fields::Unit => Vec::new(),
+    }
 }
 
 struct OutputTokenPartials {
@@ -122,14 +233,18 @@ struct OutputTokenPartials {
     field_declarations: Vec<proc_macro2::TokenStream>,
 }
 
-fn generate_output_tokens(fields: &Vec<FieldInfo>) -> OutputTokenPartials {
+fn generate_output_tokens(
+    fields: &[FieldInfo],
+    generic_type_params: &HashSet<String>,
+) -> (OutputTokenPartials, Vec<proc_macro2::TokenStream>) {
     let field_setter_functions = fields
         .iter()
         .filter(|f| !f.is_ignored)
         .map(|field| {
-            let func_name = quote::format_ident!("set_{}", field.identifier);
+            let func_name = quote::format_ident!("set_{}", field.setter_suffix);
             let identifier = &field.identifier;
             let field_type = &field.field_type;
+            let container_setter = container_setter_tokens(field);
 
             quote! {
                 pub fn #func_name<F: 'static>(mut self, f: F) -> Self where
@@ -137,53 +252,63 @@ fn generate_output_tokens(fields: &Vec<FieldInfo>) -> OutputTokenPartials {
                         self.#identifier = Box::new(f);
                         self
                 }
+
+                #container_setter
             }
         })
         .collect();
 
+    let mut required_generic_defaults: Vec<proc_macro2::Ident> = Vec::new();
+
     let field_builder_intializers = fields
         .iter()
         .map(|field| {
-            let identity = match field.field_type.clone() {
-                Type::Path(type_path) => match type_path.path.get_ident() {
-                    Some(ident) => (ident.clone(), ident.into_token_stream()),
-                    None => (
-                        type_path.path.segments.last().unwrap().ident.clone(),
-                        type_path.into_token_stream(),
-                    ),
-                },
-                _ => todo!("Type {:?} not supported", field.field_type),
-            };
-
             let identifier = &field.identifier;
-            let identity_tokens = identity.1;
 
-            if field.is_ignored {
+            if let Some(attribute_error) = &field.attribute_error {
+                quote! { #identifier: #attribute_error }
+            } else if field.is_ignored {
                 quote! { #identifier: #identifier }
-            } else {
-                let f = match identity.0.to_string().as_str() {
-                    "String" => quote! { |i| format!("{}{}", stringify!(#identifier), i).into() },
-                    "OsString" => quote! { |i| format!("{}{}", stringify!(#identifier), i).into() },
-                    "char" => quote! { |i| std::char::from_digit(i as u32, 10).unwrap_or('a') },
-                    "bool" => quote! { |i| false },
-                    "i8" | "i16" | "i32" | "u8" | "u16" | "u32" | "i64" | "i128" | "isize"
-                    | "u64" | "u128" | "usize" | "f32" | "f64" => {
-                        quote! { |i| i as #identity_tokens }
-                    }
-                    _ => {
-                        // attempt to call a builder that may be on this type
-                        // this will end up causing a compile error if the type doesn't have
-                        // the #[derive(Tlayuda)] macro.
-                        // TODO: Need to figure out a way to communicate this better in the compiler
-                        quote! { |i| #identity_tokens::tlayuda().with_index(i).build() }
-                    }
+            } else if let Some(default_expr) = &field.default_override {
+                let closure = match default_expr {
+                    Expr::Closure(_) => quote! { #default_expr },
+                    _ => quote! { move |_| (#default_expr).clone() },
                 };
-
-                quote! { #identifier: Box::new(#f) }
+                quote! { #identifier: Box::new(#closure) }
+            } else {
+                let default_value = default_value_expr(
+                    &field.field_type,
+                    &field.setter_suffix,
+                    generic_type_params,
+                    &mut required_generic_defaults,
+                );
+                quote! { #identifier: Box::new(|i| #default_value) }
             }
         })
         .collect();
 
+    // `build()` clones `#[tlayuda_ignore]` fields back out of the builder (see `build_field_values`
+    // in `entry_point`); if an ignored field's type is itself a bare generic parameter, that clone
+    // needs a `T: Clone` bound on the same impl block, mirroring the `T: Default` tracking above.
+    let required_generic_clones: Vec<proc_macro2::Ident> = fields
+        .iter()
+        .filter(|f| f.is_ignored)
+        .filter_map(|f| bare_generic_param(&f.field_type, generic_type_params))
+        .collect();
+
+    let mut seen_bounds = HashSet::new();
+    let default_bounds = required_generic_defaults
+        .iter()
+        .map(|ident| (ident, quote! { Default }))
+        .chain(
+            required_generic_clones
+                .iter()
+                .map(|ident| (ident, quote! { Clone })),
+        )
+        .filter(|(ident, bound)| seen_bounds.insert((ident.to_string(), bound.to_string())))
+        .map(|(ident, bound)| quote! { #ident: #bound })
+        .collect();
+
     let field_declarations = fields
         .iter()
         .map(
@@ -191,6 +316,7 @@ fn generate_output_tokens(fields: &Vec<FieldInfo>) -> OutputTokenPartials {
                  identifier: x,
                  field_type: t,
                  is_ignored,
+                 ..
              }| {
                 if *is_ignored {
                     quote! { #x: #t }
@@ -203,9 +329,217 @@ fn generate_output_tokens(fields: &Vec<FieldInfo>) -> OutputTokenPartials {
         )
         .collect();
 
-    OutputTokenPartials {
-        field_declarations,
-        field_builder_intializers,
-        field_setter_functions,
+    (
+        OutputTokenPartials {
+            field_declarations,
+            field_builder_intializers,
+            field_setter_functions,
+        },
+        default_bounds,
+    )
+}
+
+// Recursively builds the default-value expression for a field's type, bottoming out on the
+// primitive/string/char/bool cases or falling back to a nested `tlayuda()` builder call.
+// `Option<T>` and `Vec<T>` recurse into `T` so containers of containers (e.g. `Option<Vec<String>>`)
+// generate sensible defaults too. A bare generic type parameter of the source struct (e.g. `T`)
+// defaults via `T::default()`, recorded into `required_generic_defaults` so the caller can add
+// the matching `T: Default` bound to the builder's impl block.
+fn default_value_expr(
+    ty: &Type,
+    name: &str,
+    generic_type_params: &HashSet<String>,
+    required_generic_defaults: &mut Vec<proc_macro2::Ident>,
+) -> proc_macro2::TokenStream {
+    match ty {
+        Type::Path(type_path) => {
+            let segment = match type_path.path.segments.last() {
+                Some(segment) => segment,
+                None => {
+                    return syn::Error::new_spanned(ty, "expected a path with at least one segment")
+                        .to_compile_error()
+                }
+            };
+            let type_name = segment.ident.to_string();
+
+            if let Some(ident) = bare_generic_param(ty, generic_type_params) {
+                required_generic_defaults.push(ident);
+                return quote! { #ty::default() };
+            }
+
+            match type_name.as_str() {
+                "Option" => match inner_generic_type(segment) {
+                    Some(inner) => {
+                        let inner_value = default_value_expr(
+                            inner,
+                            name,
+                            generic_type_params,
+                            required_generic_defaults,
+                        );
+                        quote! { Some(#inner_value) }
+                    }
+                    None => missing_type_parameter_error(ty),
+                },
+                "Vec" => match inner_generic_type(segment) {
+                    Some(inner) => {
+                        let inner_value = default_value_expr(
+                            inner,
+                            name,
+                            generic_type_params,
+                            required_generic_defaults,
+                        );
+                        quote! { vec![#inner_value] }
+                    }
+                    None => missing_type_parameter_error(ty),
+                },
+                "String" => quote! { format!("{}{}", #name, i).into() },
+                "OsString" => quote! { format!("{}{}", #name, i).into() },
+                "char" => quote! { std::char::from_digit(i as u32, 10).unwrap_or('a') },
+                "bool" => quote! { false },
+                "i8" | "i16" | "i32" | "u8" | "u16" | "u32" | "i64" | "i128" | "isize"
+                | "u64" | "u128" | "usize" | "f32" | "f64" => {
+                    quote! { i as #ty }
+                }
+                _ => {
+                    // Assume this type has its own `#[derive(Tlayuda)]` and call its generated
+                    // `tlayuda()` constructor. `quote_spanned!` keeps the call on the field
+                    // type's own span, so if it turns out the type has no `tlayuda()` (no
+                    // matching derive), the resulting error points at this field instead of at
+                    // macro-generated code.
+                    let span = syn::spanned::Spanned::span(ty);
+                    quote::quote_spanned! { span => #ty::tlayuda().with_index(i).build() }
+                }
+            }
+        }
+        _ => syn::Error::new_spanned(
+            ty,
+            "Tlayuda doesn't support this field type directly; add `#[tlayuda(default = ...)]` \
+             or use a primitive, String, Option<T>, Vec<T>, or a type with its own \
+             #[derive(Tlayuda)]",
+        )
+        .to_compile_error(),
+    }
+}
+
+fn missing_type_parameter_error(ty: &Type) -> proc_macro2::TokenStream {
+    syn::Error::new_spanned(ty, "expected a single type parameter, e.g. `Option<String>`")
+        .to_compile_error()
+}
+
+// Pulls the `T` out of a `Container<T>` path segment, e.g. `Option<String>` -> `String`.
+fn inner_generic_type(segment: &PathSegment) -> Option<&Type> {
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+            GenericArgument::Type(t) => Some(t),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+// Returns the identifier if `ty` is exactly one of the source struct's generic type parameters
+// (e.g. `T`, not `Option<T>` or `Vec<T>`).
+fn bare_generic_param(
+    ty: &Type,
+    generic_type_params: &HashSet<String>,
+) -> Option<proc_macro2::Ident> {
+    let type_path = match ty {
+        Type::Path(type_path) => type_path,
+        _ => return None,
+    };
+    if type_path.qself.is_some() || type_path.path.segments.len() != 1 {
+        return None;
+    }
+    let segment = type_path.path.segments.last()?;
+    generic_type_params
+        .contains(&segment.ident.to_string())
+        .then(|| segment.ident.clone())
+}
+
+// `Option<T>` fields get an opt-out setter that forces `None`; `Vec<T>` fields get a setter that
+// generates `count` elements per build via a `(build_index, element_index) -> T` callback.
+fn container_setter_tokens(field: &FieldInfo) -> proc_macro2::TokenStream {
+    let identifier = &field.identifier;
+
+    let type_path = match &field.field_type {
+        Type::Path(type_path) => type_path,
+        _ => return quote! {},
+    };
+    let segment = match type_path.path.segments.last() {
+        Some(segment) => segment,
+        None => return quote! {},
+    };
+
+    match segment.ident.to_string().as_str() {
+        "Option" => {
+            let none_setter_name = quote::format_ident!("set_{}_none", field.setter_suffix);
+            quote! {
+                pub fn #none_setter_name(mut self) -> Self {
+                    self.#identifier = Box::new(|_| None);
+                    self
+                }
+            }
+        }
+        "Vec" => {
+            let inner_ty = match inner_generic_type(segment) {
+                Some(inner_ty) => inner_ty,
+                None => return missing_type_parameter_error(&field.field_type),
+            };
+            let with_setter_name = quote::format_ident!("set_{}_with", field.setter_suffix);
+            quote! {
+                pub fn #with_setter_name<F: 'static>(mut self, count: usize, f: F) -> Self where
+                    F: Fn(usize, usize) -> #inner_ty {
+                        self.#identifier = Box::new(move |i| (0..count).map(|element| f(i, element)).collect());
+                        self
+                }
+            }
+        }
+        _ => quote! {},
+    }
+}
+
+// Builds a `_marker: PhantomData<(...)>` field declaration and matching initializer so the
+// builder struct compiles even when a lifetime or type parameter isn't otherwise referenced by
+// any of its fields (e.g. every field is `#[tlayuda_ignore]` or the struct has no fields at all).
+fn phantom_data_parts(
+    generics: &syn::Generics,
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    let lifetime_markers = generics.lifetimes().map(|lifetime_def| {
+        let lifetime = &lifetime_def.lifetime;
+        quote! { &#lifetime () }
+    });
+    let type_markers = generics.type_params().map(|type_param| {
+        let ident = &type_param.ident;
+        quote! { #ident }
+    });
+    let markers: Vec<_> = lifetime_markers.chain(type_markers).collect();
+
+    if markers.is_empty() {
+        (quote! {}, quote! {})
+    } else {
+        (
+            quote! { _marker: std::marker::PhantomData<(#(#markers),*)>, },
+            quote! { _marker: std::marker::PhantomData, },
+        )
+    }
+}
+
+// Appends the extra bounds required for generic fields (`T: Default` for generated fields,
+// `T: Clone` for ignored ones) onto the struct's own where-clause, producing the combined
+// where-clause for the builder's inherent impl block and the source struct's `tlayuda()` impl.
+fn combined_where_clause(
+    where_clause: Option<&syn::WhereClause>,
+    extra_bounds: &[proc_macro2::TokenStream],
+) -> proc_macro2::TokenStream {
+    if extra_bounds.is_empty() {
+        return quote! { #where_clause };
+    }
+
+    match where_clause {
+        Some(existing) => {
+            let predicates = &existing.predicates;
+            quote! { where #predicates, #(#extra_bounds),* }
+        }
+        None => quote! { where #(#extra_bounds),* },
     }
 }